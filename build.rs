@@ -24,8 +24,11 @@ fn main() {
         .whitelist_type("mtc_config_t")
         .whitelist_type("train_mode_t")
         .whitelist_function("minerva_main")
+        .whitelist_function("hw_get_chip_id")
         .whitelist_var("CLOCK_BASE")
         .whitelist_var("CLK_RST_CONTROLLER_CLK_SOURCE_EMC")
+        .whitelist_var("MTC_INIT_MAGIC")
+        .whitelist_var("GP_HIDREV_MAJOR_T210B01")
         .newtype_enum("train_mode_t")
         .generate()
         .expect("failed to generate rust bindings");
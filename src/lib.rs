@@ -10,8 +10,8 @@
 //!
 //! // Create and initialize a new memory trainer. Switch to the maximum supported frequency.
 //! let mut trainer = MinervaTrainer::new(fuse::read_sdram_id())
-//!     .expect("Failed to create a memory trainer for an unknown DRAM type.");
-//! trainer.init();
+//!     .expect("Failed to create a memory trainer for an unknown DRAM type or unsupported SoC.");
+//! trainer.init().expect("Failed to initialize the memory trainer.");
 //!
 //! // Switch to a DRAM frequency of 800MHz.
 //! trainer.change_frequency(Frequency::Freq800);
@@ -29,22 +29,124 @@ use core::{mem, ptr};
 pub enum Frequency {
     /// A DRAM frequency of 204MHz.
     Freq204,
+    /// A DRAM frequency of 408MHz.
+    Freq408,
     /// A DRAM frequency of 800MHz.
     Freq800,
+    /// A DRAM frequency of 1331MHz, also known as 1333MHz.
+    Freq1333,
     /// A DRAM frequency of 1600MHz.
     Freq1600,
+    /// The overclocked DRAM frequency of 1862MHz.
+    FreqOc,
 }
 
 impl Into<i32> for Frequency {
     fn into(self) -> i32 {
         match self {
             Frequency::Freq204 => 204_000,
+            Frequency::Freq408 => 408_000,
             Frequency::Freq800 => 800_000,
+            Frequency::Freq1333 => 1_331_200,
             Frequency::Freq1600 => 1_600_000,
+            Frequency::FreqOc => 1_862_400,
         }
     }
 }
 
+/// All the DRAM frequencies that are trained by [`MinervaTrainer::init`], in ascending order.
+///
+/// [`MinervaTrainer::init`]: struct.MinervaTrainer.html#method.init
+const TRAINED_FREQUENCIES: [Frequency; 6] = [
+    Frequency::Freq204,
+    Frequency::Freq408,
+    Frequency::Freq800,
+    Frequency::Freq1333,
+    Frequency::Freq1600,
+    Frequency::FreqOc,
+];
+
+/// Errors that can occur while operating a [`MinervaTrainer`].
+///
+/// [`MinervaTrainer`]: struct.MinervaTrainer.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MinervaError {
+    /// The DRAM table that was supplied to the trainer is incompatible with the linked
+    /// Minerva binary.
+    ///
+    /// This is surfaced when `minerva_main` leaves `init_done` at a value other than
+    /// `MTC_INIT_MAGIC` after training, which usually means the chip rejected the table and
+    /// training silently did nothing.
+    IncompatibleTable,
+    /// The given SDRAM ID does not map to a known DRAM profile.
+    InvalidSdramId,
+    /// The running SoC is a T210B01 (Mariko) chip, which this crate does not support.
+    ///
+    /// The C Minerva training path and the shipped tables are T210-only; running them against
+    /// a T210B01 would corrupt its DRAM timings instead of training them.
+    Unsupported,
+}
+
+/// The individual DRAM training phases that make up a full training pass, each gated by its
+/// own bit in the `needs_training` mask.
+///
+/// These can be combined with the `|` operator and passed to
+/// [`MinervaTrainer::train_phases`] to retrain only a subset of phases, instead of paying for
+/// a complete retrain of every frequency.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TrainingPhases(u32);
+
+impl TrainingPhases {
+    /// Command/address training.
+    pub const CA: Self = TrainingPhases(1 << 0);
+    /// Command/address VREF training.
+    pub const CA_VREF: Self = TrainingPhases(1 << 1);
+    /// QUSE training.
+    pub const QUSE: Self = TrainingPhases(1 << 2);
+    /// QUSE VREF training.
+    pub const QUSE_VREF: Self = TrainingPhases(1 << 3);
+    /// Write leveling.
+    pub const WR: Self = TrainingPhases(1 << 4);
+    /// Write leveling VREF training.
+    pub const WR_VREF: Self = TrainingPhases(1 << 5);
+    /// Read leveling.
+    pub const RD: Self = TrainingPhases(1 << 6);
+    /// Read leveling VREF training.
+    pub const RD_VREF: Self = TrainingPhases(1 << 7);
+    /// Rank-swap training, for dual-rank DRAM configurations.
+    pub const RANK_SWAP: Self = TrainingPhases(1 << 8);
+    /// Self-refresh training.
+    pub const SELF_REFRESH: Self = TrainingPhases(1 << 9);
+
+    /// Every training phase, equivalent to a complete retrain.
+    pub const ALL: Self = TrainingPhases(
+        Self::CA.0
+            | Self::CA_VREF.0
+            | Self::QUSE.0
+            | Self::QUSE_VREF.0
+            | Self::WR.0
+            | Self::WR_VREF.0
+            | Self::RD.0
+            | Self::RD_VREF.0
+            | Self::RANK_SWAP.0
+            | Self::SELF_REFRESH.0,
+    );
+}
+
+impl core::ops::BitOr for TrainingPhases {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        TrainingPhases(self.0 | rhs.0)
+    }
+}
+
+impl core::ops::BitOrAssign for TrainingPhases {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
 /// The Minerva memory trainer for Tegra X1 SoCs.
 ///
 /// It is responsible for training the Tegra X1 DRAM with pre-defined profiles based on the SDRAM
@@ -54,21 +156,27 @@ impl Into<i32> for Frequency {
 /// to be initialized with [`MinervaTrainer::init`] afterwards before it can be used freely.
 pub struct MinervaTrainer {
     cfg: raw::mtc_config_t,
-    tables: &'static [raw::emc_table_t; 10],
+    tables: [raw::emc_table_t; 10],
 }
 
 impl MinervaTrainer {
     /// Creates a new DRAM trainer that will use the table that selects the correct DRAM
     /// profile based on the supplied SDRAM ID.
     ///
-    /// Returns `None` if the SDRAM ID is invalid.
-    pub fn new(sdram_id: u32) -> Option<Self> {
-        let profile = dram_profile::get_by_sdram_id(sdram_id)?;
+    /// Returns [`MinervaError::InvalidSdramId`] if the SDRAM ID is invalid, and
+    /// [`MinervaError::Unsupported`] if the running SoC is a T210B01 (Mariko) chip, for which
+    /// this crate's training path and tables do not apply.
+    pub fn new(sdram_id: u32) -> Result<Self, MinervaError> {
+        if read_chip_id() == raw::GP_HIDREV_MAJOR_T210B01 {
+            return Err(MinervaError::Unsupported);
+        }
+
+        let profile = dram_profile::get_by_sdram_id(sdram_id).ok_or(MinervaError::InvalidSdramId)?;
 
         let mut cfg = unsafe { mem::zeroed::<raw::mtc_config_t>() };
         cfg.sdram_id = sdram_id;
 
-        Some(MinervaTrainer {
+        Ok(MinervaTrainer {
             tables: transform_table(profile),
             cfg,
         })
@@ -77,21 +185,25 @@ impl MinervaTrainer {
     /// Initializes this DRAM trainer.
     ///
     /// This method **has** to be called in advance before any DRAM training can be done.
-    pub fn init(&mut self) {
-        self.cfg.mtc_table = self.tables.as_ptr() as *mut _;
+    ///
+    /// Returns [`MinervaError::IncompatibleTable`] if the chip rejected the supplied table
+    /// instead of training it, which would otherwise go unnoticed and leave subsequent calls
+    /// to [`change_frequency`](Self::change_frequency) operating on an untrained trainer.
+    pub fn init(&mut self) -> Result<(), MinervaError> {
+        self.sync_table_ptr();
+        self.cfg.table_entries = count_table_entries(&self.tables);
 
         let ram_index = (0..10)
             .find(|idx| read_clk_src_emc() == self.tables[*idx].clk_src_emc)
             .unwrap_or(0);
 
         self.cfg.rate_from = self.tables[ram_index].rate_khz as i32;
-        self.cfg.rate_to = Frequency::Freq204.into();
         self.cfg.train_mode = raw::train_mode_t::OP_TRAIN.0;
-        unsafe { raw::minerva_main(&mut self.cfg) };
-        self.cfg.rate_to = Frequency::Freq800.into();
-        unsafe { raw::minerva_main(&mut self.cfg) };
-        self.cfg.rate_to = Frequency::Freq1600.into();
-        unsafe { raw::minerva_main(&mut self.cfg) };
+        self.cfg.needs_training = TrainingPhases::ALL.0;
+        for freq in TRAINED_FREQUENCIES.iter().copied() {
+            self.cfg.rate_to = freq.into();
+            unsafe { raw::minerva_main(&mut self.cfg) };
+        }
 
         // FSP WAR.
         self.cfg.train_mode = raw::train_mode_t::OP_SWITCH.0;
@@ -101,10 +213,18 @@ impl MinervaTrainer {
         // Switch to highest frequency of 1600MHz.
         self.cfg.rate_to = Frequency::Freq1600.into();
         unsafe { raw::minerva_main(&mut self.cfg) };
+
+        if self.cfg.init_done != raw::MTC_INIT_MAGIC {
+            return Err(MinervaError::IncompatibleTable);
+        }
+
+        Ok(())
     }
 
     /// Changes the DRAM frequency of this DRAM trainer.
     pub fn change_frequency(&mut self, freq: Frequency) {
+        self.sync_table_ptr();
+
         let freq = freq.into();
 
         if self.cfg.rate_from != freq {
@@ -117,11 +237,186 @@ impl MinervaTrainer {
     /// Performs periodic memory training compensation on the DRAM with the profile
     /// selected by this DRAM trainer.
     pub fn periodic_training(&mut self) {
+        self.sync_table_ptr();
+
         if self.cfg.rate_from == Frequency::Freq1600.into() {
             self.cfg.train_mode = raw::train_mode_t::OP_PERIODIC_TRAIN.0;
             unsafe { raw::minerva_main(&mut self.cfg) };
         }
     }
+
+    /// Performs temperature-compensation training to adjust DRAM timings for temperature
+    /// drift, independently of [`periodic_training`](Self::periodic_training).
+    ///
+    /// Unlike periodic training, this is not gated on the current frequency and may be
+    /// issued at whatever rate the trainer is currently running at.
+    pub fn temperature_compensation(&mut self) {
+        self.sync_table_ptr();
+
+        self.cfg.train_mode = raw::train_mode_t::OP_TEMP_COMPENSATE.0;
+        unsafe { raw::minerva_main(&mut self.cfg) };
+    }
+
+    /// Retrains only the given [`TrainingPhases`] of `freq`, instead of a complete retrain.
+    ///
+    /// Useful for recovering from a marginal link by re-running just read/write leveling, or
+    /// just the VREF sub-phases, without retraining phases that are already known-good.
+    ///
+    /// This leaves `needs_training` set to `phases` afterward; [`init`](Self::init) and
+    /// [`prep_boot_l4t`](Self::prep_boot_l4t) reset it to [`TrainingPhases::ALL`] themselves
+    /// before their own `OP_TRAIN` passes, so a prior partial retrain never leaks into them.
+    pub fn train_phases(&mut self, freq: Frequency, phases: TrainingPhases) {
+        self.sync_table_ptr();
+
+        self.cfg.rate_to = freq.into();
+        self.cfg.needs_training = phases.0;
+        self.cfg.train_mode = raw::train_mode_t::OP_TRAIN.0;
+        unsafe { raw::minerva_main(&mut self.cfg) };
+    }
+
+    /// Settles the DRAM at a safe frequency of 800MHz in preparation for handing off to a
+    /// next-stage bootloader.
+    pub fn prep_boot_freq(&mut self) {
+        self.change_frequency(Frequency::Freq800);
+    }
+
+    /// Trains every entry of the EMC table and then parks the DRAM at a low init frequency of
+    /// 204MHz, so a next-stage L4T kernel can take over DVFS cleanly.
+    ///
+    /// Walks the real `table_entries` count off the config rather than assuming the fixed
+    /// `[emc_table_t; 10]` shape, since not every profile populates all ten slots.
+    pub fn prep_boot_l4t(&mut self) {
+        self.sync_table_ptr();
+
+        self.cfg.train_mode = raw::train_mode_t::OP_TRAIN.0;
+        self.cfg.needs_training = TrainingPhases::ALL.0;
+        for idx in 0..self.cfg.table_entries as usize {
+            self.cfg.rate_to = self.tables[idx].rate_khz as i32;
+            unsafe { raw::minerva_main(&mut self.cfg) };
+        }
+
+        self.change_frequency(Frequency::Freq204);
+    }
+
+    /// Overrides the Latency Allowance register at `index` within the LA register block of
+    /// every table entry with `value`.
+    ///
+    /// This rewrites the mutable table copy this trainer holds, so a tightened LA profile can
+    /// be applied ahead of `OP_TRAIN`/`OP_SWITCH` without hand-editing the underlying
+    /// 49280-byte binary blob.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is outside the bounds of the `la_scale_regs` block.
+    pub fn set_latency_allowance(&mut self, index: usize, value: u32) {
+        for table in self.tables.iter_mut() {
+            assert!(
+                index < table.la_scale_regs.len(),
+                "latency allowance register index {} is out of bounds",
+                index
+            );
+            table.la_scale_regs[index] = value;
+        }
+    }
+
+    /// Overrides the SDMMC1 Latency Allowance register across every table entry.
+    ///
+    /// This is the LA slot L4T handoff needs tightened before training. Only the T210 offset
+    /// is used here, since [`MinervaTrainer::new`] already rejects T210B01 chips, whose LA
+    /// register file is laid out differently, before a trainer can be constructed.
+    pub fn set_sdmmc1_latency_allowance(&mut self, value: u32) {
+        self.set_latency_allowance(SDMMC1_LA_INDEX, value);
+    }
+
+    /// Re-points `cfg.mtc_table` at this trainer's own `tables` array.
+    ///
+    /// `tables` is an inline field rather than a `'static` reference, so its address changes
+    /// whenever the `MinervaTrainer` is moved (for instance into a [`MinervaDriver`]). Every
+    /// method that hands `cfg` to `minerva_main` calls this first so the pointer can never go
+    /// stale and dereference freed or relocated memory.
+    fn sync_table_ptr(&mut self) {
+        self.cfg.mtc_table = self.tables.as_mut_ptr();
+    }
+}
+
+/// The index of the SDMMC1 Latency Allowance register within an [`raw::emc_table_t`] entry's
+/// `la_scale_regs` block on a T210 chip.
+///
+/// This mirrors the position of `MC_LATENCY_ALLOWANCE_SDMMC1_0` as laid out in
+/// `src/minerva_tc/mtc/mtc_mc_emc_regs.h`; update it alongside that header if the LA register
+/// file's layout ever changes.
+const SDMMC1_LA_INDEX: usize = 14;
+
+/// The cadence, in milliseconds, at which [`MinervaDriver::run_maintenance`] issues a
+/// [`MinervaTrainer::periodic_training`] pass.
+const PERIODIC_TRAINING_INTERVAL_MS: u32 = 100;
+
+/// The cadence, in milliseconds, at which [`MinervaDriver::run_maintenance`] issues a
+/// [`MinervaTrainer::temperature_compensation`] pass.
+const TEMPERATURE_COMPENSATION_INTERVAL_MS: u32 = 1000;
+
+/// Drives the periodic and temperature-compensation maintenance cadences of a
+/// [`MinervaTrainer`] so embedded callers don't have to reimplement the timing themselves.
+///
+/// Feed it the elapsed time since the last call through [`run_maintenance`](Self::run_maintenance)
+/// from a timer loop, and it will dispatch [`MinervaTrainer::periodic_training`] roughly every
+/// 100ms and [`MinervaTrainer::temperature_compensation`] roughly every 1000ms.
+///
+/// It derefs to the wrapped [`MinervaTrainer`], so the rest of the trainer's API is still
+/// reachable through the driver; use [`into_inner`](Self::into_inner) to reclaim it outright.
+pub struct MinervaDriver {
+    trainer: MinervaTrainer,
+    periodic_elapsed_ms: u32,
+    temperature_elapsed_ms: u32,
+}
+
+impl MinervaDriver {
+    /// Wraps a [`MinervaTrainer`] to drive its maintenance cadences.
+    pub fn new(trainer: MinervaTrainer) -> Self {
+        MinervaDriver {
+            trainer,
+            periodic_elapsed_ms: 0,
+            temperature_elapsed_ms: 0,
+        }
+    }
+
+    /// Unwraps this driver, returning the underlying [`MinervaTrainer`] so the rest of its
+    /// API (e.g. [`change_frequency`](MinervaTrainer::change_frequency) or
+    /// [`train_phases`](MinervaTrainer::train_phases)) can be used directly.
+    pub fn into_inner(self) -> MinervaTrainer {
+        self.trainer
+    }
+
+    /// Advances this driver by `elapsed_ms` milliseconds, dispatching periodic training and
+    /// temperature compensation whenever their respective intervals have elapsed.
+    pub fn run_maintenance(&mut self, elapsed_ms: u32) {
+        self.periodic_elapsed_ms += elapsed_ms;
+        self.temperature_elapsed_ms += elapsed_ms;
+
+        while self.periodic_elapsed_ms >= PERIODIC_TRAINING_INTERVAL_MS {
+            self.trainer.periodic_training();
+            self.periodic_elapsed_ms -= PERIODIC_TRAINING_INTERVAL_MS;
+        }
+
+        while self.temperature_elapsed_ms >= TEMPERATURE_COMPENSATION_INTERVAL_MS {
+            self.trainer.temperature_compensation();
+            self.temperature_elapsed_ms -= TEMPERATURE_COMPENSATION_INTERVAL_MS;
+        }
+    }
+}
+
+impl core::ops::Deref for MinervaDriver {
+    type Target = MinervaTrainer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.trainer
+    }
+}
+
+impl core::ops::DerefMut for MinervaDriver {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.trainer
+    }
 }
 
 fn read_clk_src_emc() -> u32 {
@@ -129,13 +424,29 @@ fn read_clk_src_emc() -> u32 {
     unsafe { ptr::read_volatile(addr as *const u32) }
 }
 
-fn transform_table(table: &'static [u8; 49280]) -> &'static [raw::emc_table_t; 10] {
+/// Reads the chip ID off the APB misc HIDREV register to tell apart T210 and T210B01 SoCs.
+fn read_chip_id() -> u32 {
+    unsafe { raw::hw_get_chip_id() }
+}
+
+/// Counts how many entries of a profile's table are actually populated.
+///
+/// Not every DRAM profile fills all ten slots of the fixed-size table; unused trailing slots
+/// are left zeroed, so the first entry with a `rate_khz` of `0` marks the end of the
+/// populated range.
+fn count_table_entries(tables: &[raw::emc_table_t; 10]) -> u32 {
+    tables.iter().take_while(|table| table.rate_khz != 0).count() as u32
+}
+
+fn transform_table(table: &'static [u8; 49280]) -> [raw::emc_table_t; 10] {
     use core::convert::TryInto;
 
     // SAFETY: The size of `raw::emc_table_t` is equal to the length of the given table
     //         divided by 10.
-    let slice = unsafe { core::slice::from_raw_parts(table.as_ptr() as *const _, 10) };
-    slice.try_into().unwrap()
+    let slice: &[raw::emc_table_t] =
+        unsafe { core::slice::from_raw_parts(table.as_ptr() as *const _, 10) };
+    let array_ref: &[raw::emc_table_t; 10] = slice.try_into().unwrap();
+    *array_ref
 }
 
 /// DRAM profiles for Minerva memory training.